@@ -0,0 +1,87 @@
+// db/conn.rs
+
+use deadpool_postgres::{Client, Transaction};
+use tokio_postgres::types::ToSql;
+use tokio_postgres::{Error, Row, Statement};
+
+/// Unifies a pooled client and an open transaction behind one type so a
+/// query can run either standalone or as part of a larger transaction
+/// without the caller needing two code paths.
+pub enum DbConn<'a> {
+    Pooled(&'a Client),
+    Tx(&'a Transaction<'a>),
+    /// Placeholder passed to a `PersonRepository` that is itself
+    /// backed by something other than Postgres (e.g. an in-memory
+    /// implementation under test). Such implementations never call through
+    /// to `query`/`execute` below, so there's nothing for this variant to
+    /// hold — it exists purely so tests can construct *some* `DbConn` to
+    /// satisfy a trait signature without opening a real connection.
+    #[cfg(test)]
+    Test,
+}
+
+impl<'a> DbConn<'a> {
+    pub async fn query(
+        &self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Vec<Row>, Error> {
+        match self {
+            DbConn::Pooled(client) => client.query(statement, params).await,
+            DbConn::Tx(tx) => tx.query(statement, params).await,
+            #[cfg(test)]
+            DbConn::Test => unreachable!("DbConn::Test never executes real queries"),
+        }
+    }
+
+    pub async fn query_opt(
+        &self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Option<Row>, Error> {
+        match self {
+            DbConn::Pooled(client) => client.query_opt(statement, params).await,
+            DbConn::Tx(tx) => tx.query_opt(statement, params).await,
+            #[cfg(test)]
+            DbConn::Test => unreachable!("DbConn::Test never executes real queries"),
+        }
+    }
+
+    pub async fn query_one(
+        &self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<Row, Error> {
+        match self {
+            DbConn::Pooled(client) => client.query_one(statement, params).await,
+            DbConn::Tx(tx) => tx.query_one(statement, params).await,
+            #[cfg(test)]
+            DbConn::Test => unreachable!("DbConn::Test never executes real queries"),
+        }
+    }
+
+    pub async fn execute(
+        &self,
+        statement: &Statement,
+        params: &[&(dyn ToSql + Sync)],
+    ) -> Result<u64, Error> {
+        match self {
+            DbConn::Pooled(client) => client.execute(statement, params).await,
+            DbConn::Tx(tx) => tx.execute(statement, params).await,
+            #[cfg(test)]
+            DbConn::Test => unreachable!("DbConn::Test never executes real queries"),
+        }
+    }
+}
+
+impl<'a> From<&'a Client> for DbConn<'a> {
+    fn from(client: &'a Client) -> Self {
+        DbConn::Pooled(client)
+    }
+}
+
+impl<'a> From<&'a Transaction<'a>> for DbConn<'a> {
+    fn from(tx: &'a Transaction<'a>) -> Self {
+        DbConn::Tx(tx)
+    }
+}
@@ -1,8 +1,11 @@
+pub mod conn;
 pub mod outbox;
 pub mod person;
 
 // Re-export commonly used items for convenience
+pub use conn::DbConn;
 pub use outbox::Outbox;
 pub use person::NewPersonRecord;
 pub use person::PersonDb;
 pub use person::PersonRecord;
+pub use person::PersonRepository;
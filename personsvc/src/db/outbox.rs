@@ -1,16 +1,18 @@
 // db/outbox.rs
 
 use chrono::NaiveDateTime;
-use deadpool_postgres::{Pool, Transaction};
 use opentelemetry::trace::Tracer;
 use opentelemetry::{global, trace::Span};
 use tokio_postgres::{Client, Error, Statement};
 use tracing::error;
 use uuid::Uuid;
 
+use crate::db::conn::DbConn;
+
 #[derive(Debug)]
 pub struct Outbox {
     insert_stmt: Statement,
+    notify_stmt: Statement,
     get_pending_stmt: Statement,
     clear_event_stmt: Statement,
     errored_event_stmt: Statement,
@@ -27,17 +29,34 @@ pub struct OutboxMessage {
     pub published_date_time: Option<NaiveDateTime>,
     pub error_count: i32,
     pub error_message: Option<String>,
+    pub next_attempt_date_time: Option<NaiveDateTime>,
 }
 
 impl Outbox {
+    /// Prepares statements against the existing `"Outbox"` table. This repo
+    /// tracks no schema/migrations, so the `NextAttemptDateTime` column the
+    /// backoff statements below read and write has to be added by hand
+    /// before deploying this version, or this constructor fails to prepare
+    /// the pending-messages and errored-event statements. The statements
+    /// name it unquoted, like the table's other columns, so add it unquoted:
+    ///
+    /// ```sql
+    /// ALTER TABLE "Outbox" ADD COLUMN NextAttemptDateTime TIMESTAMP;
+    /// ```
     pub async fn new(client: &Client) -> Result<Self, Error> {
         let insert_stmt = client.prepare(
             "INSERT INTO \"Outbox\" (Id, Topic, EventName, Payload, \"Status\", CreatedDateTime) VALUES ($1, $2, $3, $4, $5, NOW())"
         ).await?;
 
+        let notify_stmt = client
+            .prepare("SELECT pg_notify('outbox_new', $1::text)")
+            .await?;
+
         let get_pending_stmt = client
             .prepare(
-                "SELECT * FROM \"Outbox\" WHERE PublishedDateTime IS NULL AND ErrorCount < 10 LIMIT 50",
+                "SELECT * FROM \"Outbox\" WHERE PublishedDateTime IS NULL AND ErrorCount < 10 \
+                 AND (NextAttemptDateTime IS NULL OR NextAttemptDateTime <= NOW()) \
+                 ORDER BY CreatedDateTime FOR UPDATE SKIP LOCKED LIMIT 50",
             )
             .await?;
 
@@ -45,23 +64,28 @@ impl Outbox {
             "UPDATE \"Outbox\" SET \"Status\" = 'Published', ErrorCount = 0, PublishedDateTime = NOW() WHERE Id = $1"
         ).await?;
 
+        // Exponential backoff, base delay 1 second, capped at 5 minutes.
         let errored_event_stmt = client
             .prepare(
-                "UPDATE \"Outbox\" SET \"Status\" = 'Error', ErrorCount = ErrorCount + 1 WHERE Id = $1",
+                "UPDATE \"Outbox\" SET \"Status\" = 'Error', ErrorCount = ErrorCount + 1, \
+                 ErrorMessage = $2, \
+                 NextAttemptDateTime = NOW() + LEAST(INTERVAL '5 minutes', INTERVAL '1 second' * POWER(2, ErrorCount)) \
+                 WHERE Id = $1",
             )
             .await?;
 
         Ok(Self {
             insert_stmt,
+            notify_stmt,
             get_pending_stmt,
             clear_event_stmt,
             errored_event_stmt,
         })
     }
 
-    pub async fn insert<'a, T: serde::Serialize>(
+    pub async fn insert<T: serde::Serialize>(
         &self,
-        txn: &Transaction<'a>,
+        conn: &DbConn<'_>,
         topic: &str,
         event_name: &str,
         payload: &T,
@@ -71,27 +95,47 @@ impl Outbox {
         let id = Uuid::new_v4();
         let status = "Unpublished";
 
-        let result = txn
+        let result = conn
             .execute(
                 &self.insert_stmt,
                 &[&id, &topic, &event_name, &payload_str, &status],
             )
             .await;
-        span.end();
 
-        match result {
-            Ok(_) => Ok(()),
+        let result = match result {
+            Ok(n) => n,
             Err(e) => {
+                span.end();
                 error!("Error inserting into Outbox: {:?}", e);
-                Err(e)
+                return Err(e);
             }
+        };
+
+        // Wake any listening relay worker instead of making it wait for its
+        // fallback poll tick.
+        if let Err(e) = conn.execute(&self.notify_stmt, &[&id.to_string()]).await {
+            span.end();
+            error!("Error notifying outbox_new for {}: {:?}", id, e);
+            return Err(e);
         }
+
+        span.end();
+        let _ = result;
+        Ok(())
     }
 
-    pub async fn get_pending_messages(&self, client: &Client) -> Result<Vec<OutboxMessage>, Error> {
+    /// Claims up to 50 pending rows with `FOR UPDATE SKIP LOCKED`. Must be
+    /// called inside a transaction so the row locks it takes are held until
+    /// the caller commits after marking each claimed row published or
+    /// errored — this is what lets multiple relay instances compete for
+    /// work instead of double-publishing the same rows.
+    pub async fn get_pending_messages(
+        &self,
+        conn: &DbConn<'_>,
+    ) -> Result<Vec<OutboxMessage>, Error> {
         let mut span = global::tracer("outbox_db").start("get_pending_messages");
 
-        let result = client.query(&self.get_pending_stmt, &[]).await;
+        let result = conn.query(&self.get_pending_stmt, &[]).await;
         span.end();
 
         match result {
@@ -107,6 +151,7 @@ impl Outbox {
                     published_date_time: row.get("PublishedDateTime"),
                     error_count: row.get("ErrorCount"),
                     error_message: row.get("ErrorMessage"),
+                    next_attempt_date_time: row.get("NextAttemptDateTime"),
                 })
                 .collect()),
             Err(e) => {
@@ -116,10 +161,10 @@ impl Outbox {
         }
     }
 
-    pub async fn clear_event(&self, client: &Client, id: Uuid) -> Result<(), Error> {
+    pub async fn clear_event(&self, conn: &DbConn<'_>, id: Uuid) -> Result<(), Error> {
         let mut span = global::tracer("outbox_db").start("clear_event");
 
-        let result = client.execute(&self.clear_event_stmt, &[&id]).await;
+        let result = conn.execute(&self.clear_event_stmt, &[&id]).await;
         span.end();
 
         match result {
@@ -131,10 +176,17 @@ impl Outbox {
         }
     }
 
-    pub async fn errored_event(&self, client: &Client, id: Uuid) -> Result<(), Error> {
+    pub async fn errored_event(
+        &self,
+        conn: &DbConn<'_>,
+        id: Uuid,
+        error_message: &str,
+    ) -> Result<(), Error> {
         let mut span = global::tracer("outbox_db").start("errored_event");
 
-        let result = client.execute(&self.errored_event_stmt, &[&id]).await;
+        let result = conn
+            .execute(&self.errored_event_stmt, &[&id, &error_message])
+            .await;
         span.end();
 
         match result {
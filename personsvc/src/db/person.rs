@@ -1,13 +1,46 @@
 // db/user_name.rs
 
+use async_trait::async_trait;
 use chrono::NaiveDateTime;
 use opentelemetry::trace::Tracer;
 use opentelemetry::{global, trace::Span};
 use serde::{Deserialize, Serialize};
-use tokio_postgres::{Client, Error, Statement, Transaction};
+use tokio_postgres::{Client, Error, Statement};
 use tracing::error;
 use uuid::Uuid;
 
+use crate::db::conn::DbConn;
+
+/// The CRUD surface `PersonService` needs from a storage backend, pulled
+/// out of the concrete Postgres type so the service can be built against
+/// an in-memory implementation in tests and, eventually, other backends.
+///
+/// Every method takes a `DbConn` rather than a pooled client or a
+/// transaction specifically, so a read can participate in an open
+/// transaction (e.g. read-your-writes validation before a commit) instead
+/// of being forced down a separate non-transactional path.
+#[async_trait]
+pub trait PersonRepository: Send + Sync {
+    async fn get_by_id(&self, conn: &DbConn<'_>, id: Uuid) -> Result<Option<PersonRecord>, Error>;
+
+    async fn list(
+        &self,
+        conn: &DbConn<'_>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<PersonRecord>, Error>;
+
+    async fn create(
+        &self,
+        conn: &DbConn<'_>,
+        user: &NewPersonRecord,
+    ) -> Result<PersonRecord, Error>;
+
+    async fn update(&self, conn: &DbConn<'_>, user: &PersonRecord) -> Result<PersonRecord, Error>;
+
+    async fn delete(&self, conn: &DbConn<'_>, id: &Uuid) -> Result<bool, Error>;
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NewPersonRecord {
     pub id: Uuid,
@@ -67,18 +100,14 @@ impl PersonDb {
             delete_stmt,
         })
     }
+}
 
-    pub async fn get_by_id(
-        &self,
-        client: &Client,
-        id: Uuid,
-    ) -> Result<Option<PersonRecord>, Error> {
+#[async_trait]
+impl PersonRepository for PersonDb {
+    async fn get_by_id(&self, conn: &DbConn<'_>, id: Uuid) -> Result<Option<PersonRecord>, Error> {
         let mut span = global::tracer("person_db").start("get_by_id");
 
-        //let result = client.query_opt(&self.get_by_id_stmt, &[&id]).await;
-        let result = client
-            .query_opt("SELECT * FROM \"UserName\" WHERE Id = $1", &[&id])
-            .await;
+        let result = conn.query_opt(&self.get_by_id_stmt, &[&id]).await;
 
         span.end();
 
@@ -100,15 +129,15 @@ impl PersonDb {
         }
     }
 
-    pub async fn list(
+    async fn list(
         &self,
-        client: &Client,
+        conn: &DbConn<'_>,
         offset: i64,
         limit: i64,
     ) -> Result<Vec<PersonRecord>, Error> {
         let mut span = global::tracer("person_db").start("list");
 
-        let result = client.query(&self.list_stmt, &[&offset, &limit]).await;
+        let result = conn.query(&self.list_stmt, &[&offset, &limit]).await;
         span.end();
 
         match result {
@@ -131,14 +160,14 @@ impl PersonDb {
         }
     }
 
-    pub async fn create<'a>(
+    async fn create(
         &self,
-        txn: &Transaction<'a>,
+        conn: &DbConn<'_>,
         user: &NewPersonRecord,
     ) -> Result<PersonRecord, Error> {
         let mut span = global::tracer("person_db").start("create");
 
-        let result = txn
+        let result = conn
             .query_one(
                 &self.create_stmt,
                 &[
@@ -169,14 +198,10 @@ impl PersonDb {
         }
     }
 
-    pub async fn update<'a>(
-        &self,
-        txn: &Transaction<'a>,
-        user: &PersonRecord,
-    ) -> Result<PersonRecord, Error> {
+    async fn update(&self, conn: &DbConn<'_>, user: &PersonRecord) -> Result<PersonRecord, Error> {
         let mut span = global::tracer("person_db").start("update");
 
-        let result = txn
+        let result = conn
             .query_one(
                 &self.update_stmt,
                 &[
@@ -207,10 +232,10 @@ impl PersonDb {
         }
     }
 
-    pub async fn delete<'a>(&self, txn: &Transaction<'a>, id: &Uuid) -> Result<bool, Error> {
+    async fn delete(&self, conn: &DbConn<'_>, id: &Uuid) -> Result<bool, Error> {
         let mut span = global::tracer("person_db").start("delete");
 
-        let result = txn.execute(&self.delete_stmt, &[&id]).await;
+        let result = conn.execute(&self.delete_stmt, &[&id]).await;
         span.end();
 
         match result {
@@ -222,3 +247,122 @@ impl PersonDb {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::io;
+    use std::sync::Mutex;
+
+    /// In-memory `PersonRepository` used only by this module's tests — the
+    /// "construct with an in-memory implementation for tests, no live
+    /// Postgres needed" chunk0-4 introduced the trait to enable, exercised
+    /// here for the first time. Ignores `conn` entirely; callers pass
+    /// `DbConn::Test` since there's no real connection to hand it.
+    #[derive(Default)]
+    struct InMemoryPersonRepository {
+        records: Mutex<HashMap<Uuid, PersonRecord>>,
+    }
+
+    #[async_trait]
+    impl PersonRepository for InMemoryPersonRepository {
+        async fn get_by_id(
+            &self,
+            _conn: &DbConn<'_>,
+            id: Uuid,
+        ) -> Result<Option<PersonRecord>, Error> {
+            Ok(self.records.lock().unwrap().get(&id).cloned())
+        }
+
+        async fn list(
+            &self,
+            _conn: &DbConn<'_>,
+            offset: i64,
+            limit: i64,
+        ) -> Result<Vec<PersonRecord>, Error> {
+            let mut records: Vec<_> = self.records.lock().unwrap().values().cloned().collect();
+            records.sort_by_key(|r| r.created_date_time);
+            Ok(records
+                .into_iter()
+                .skip(offset.max(0) as usize)
+                .take(limit.max(0) as usize)
+                .collect())
+        }
+
+        async fn create(
+            &self,
+            _conn: &DbConn<'_>,
+            user: &NewPersonRecord,
+        ) -> Result<PersonRecord, Error> {
+            let record = PersonRecord {
+                id: user.id,
+                first_name: user.first_name.clone(),
+                middle_name: user.middle_name.clone(),
+                last_name: user.last_name.clone(),
+                suffix: user.suffix.clone(),
+                created_date_time: user.created_date_time,
+                updated_date_time: None,
+            };
+            self.records
+                .lock()
+                .unwrap()
+                .insert(record.id, record.clone());
+            Ok(record)
+        }
+
+        async fn update(
+            &self,
+            _conn: &DbConn<'_>,
+            user: &PersonRecord,
+        ) -> Result<PersonRecord, Error> {
+            let mut records = self.records.lock().unwrap();
+            if !records.contains_key(&user.id) {
+                return Err(io::Error::new(io::ErrorKind::NotFound, "person not found").into());
+            }
+            records.insert(user.id, user.clone());
+            Ok(user.clone())
+        }
+
+        async fn delete(&self, _conn: &DbConn<'_>, id: &Uuid) -> Result<bool, Error> {
+            Ok(self.records.lock().unwrap().remove(id).is_some())
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_repository_round_trips_crud() {
+        let repo = InMemoryPersonRepository::default();
+        let conn = DbConn::Test;
+
+        let new_person = NewPersonRecord {
+            id: Uuid::new_v4(),
+            first_name: "Ada".to_string(),
+            middle_name: None,
+            last_name: "Lovelace".to_string(),
+            suffix: None,
+            created_date_time: chrono::Utc::now().naive_utc(),
+        };
+
+        let created = repo.create(&conn, &new_person).await.unwrap();
+        assert_eq!(created.id, new_person.id);
+
+        let fetched = repo.get_by_id(&conn, created.id).await.unwrap();
+        assert_eq!(fetched.unwrap().last_name, "Lovelace");
+
+        let mut updated = created.clone();
+        updated.last_name = "Byron".to_string();
+        let updated = repo.update(&conn, &updated).await.unwrap();
+        assert_eq!(updated.last_name, "Byron");
+
+        let listed = repo.list(&conn, 0, 10).await.unwrap();
+        assert_eq!(listed.len(), 1);
+
+        let deleted = repo.delete(&conn, &created.id).await.unwrap();
+        assert!(deleted);
+        assert!(repo
+            .get_by_id(&conn, created.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,79 @@
+// handlers/events_handler.rs
+
+use std::sync::Arc;
+
+use actix::{Actor, ActorContext, AsyncContext, StreamHandler};
+use actix_web::{web, Error, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
+use tracing::error;
+
+use crate::services::EventBroadcaster;
+
+/// One actor instance per connected client, forwarding frames from the
+/// shared `EventBroadcaster` onto that client's WebSocket.
+pub struct PersonEventsSession {
+    broadcaster: Arc<EventBroadcaster>,
+}
+
+impl Actor for PersonEventsSession {
+    type Context = ws::WebsocketContext<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        // Registering the broadcast receiver as a stream on the actor's own
+        // context (rather than spawning a detached task that loops on
+        // `recv()`) ties its lifetime to the actor: actix drops the stream
+        // as soon as the actor stops, so a disconnected client releases its
+        // subscriber slot immediately instead of leaking a task that wakes
+        // on every future event forever.
+        ctx.add_stream(BroadcastStream::new(self.broadcaster.subscribe()));
+    }
+}
+
+impl StreamHandler<Result<String, BroadcastStreamRecvError>> for PersonEventsSession {
+    fn handle(&mut self, msg: Result<String, BroadcastStreamRecvError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(frame) => ctx.text(frame),
+            Err(BroadcastStreamRecvError::Lagged(skipped)) => {
+                error!("WebSocket client lagged, dropped {} event frame(s)", skipped);
+            }
+        }
+    }
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for PersonEventsSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Ok(_) => {}
+            Err(err) => {
+                error!("Person events WebSocket protocol error: {:?}", err);
+                ctx.stop();
+            }
+        }
+    }
+}
+
+/// `GET /api/v1/person/events` — upgrades to a WebSocket that streams
+/// `PersonCreated`/`PersonUpdated`/`PersonDeleted` frames as `PersonService`
+/// commits the corresponding transaction. The outbox relay and this feed
+/// are independent consumers of the same commit: the relay durably
+/// publishes to the broker while `broadcast_on_commit` fans the frame out
+/// to whatever WebSocket clients happen to be subscribed at that moment.
+pub async fn person_events(
+    req: HttpRequest,
+    stream: web::Payload,
+    broadcaster: web::Data<Arc<EventBroadcaster>>,
+) -> Result<HttpResponse, Error> {
+    ws::start(
+        PersonEventsSession {
+            broadcaster: broadcaster.get_ref().clone(),
+        },
+        &req,
+        stream,
+    )
+}
@@ -0,0 +1,2 @@
+pub mod events_handler;
+pub mod person_handler;
@@ -4,6 +4,7 @@ use std::default;
 use std::sync::Arc;
 
 use crate::db::{person::PersonDb, NewPersonRecord, PersonRecord};
+use crate::services::error::PersonServiceError;
 use crate::services::person_service::PersonService;
 use actix_web::{web, HttpResponse, Responder};
 use chrono;
@@ -13,6 +14,18 @@ use tokio_postgres::GenericClient;
 use tracing::error;
 use uuid::Uuid;
 
+/// Maps a `PersonServiceError` to the HTTP status a client should see,
+/// so a unique-constraint hit surfaces as 409 instead of an opaque 500.
+fn error_response(err: &PersonServiceError) -> HttpResponse {
+    match err {
+        PersonServiceError::NotFound => HttpResponse::NotFound().finish(),
+        PersonServiceError::PoolTimeout => HttpResponse::ServiceUnavailable().finish(),
+        PersonServiceError::Conflict { .. } => HttpResponse::Conflict().finish(),
+        PersonServiceError::Serialization => HttpResponse::Conflict().finish(),
+        PersonServiceError::Db { .. } => HttpResponse::InternalServerError().finish(),
+    }
+}
+
 #[derive(Deserialize)]
 pub struct CreateUserRequest {
     first_name: String,
@@ -46,14 +59,16 @@ pub async fn get_user_by_id(
         })
         .unwrap();
 
-    let user = service
+    let user = match service
         .get_user_by_id(db_pool.get_ref(), id.into_inner())
         .await
-        .map_err(|err| {
+    {
+        Ok(user) => user,
+        Err(err) => {
             error!("Error retrieving record from database: {}", err);
-            HttpResponse::InternalServerError().finish();
-        })
-        .unwrap();
+            return error_response(&err);
+        }
+    };
     match (user) {
         Some(user) => HttpResponse::Ok().json(UserResponse {
             id: user.id,
@@ -99,7 +114,7 @@ pub async fn list_users(
         ),
         Err(e) => {
             error!("Error listing users: {:?}", e);
-            HttpResponse::InternalServerError().finish()
+            error_response(&e)
         }
     }
 }
@@ -118,17 +133,6 @@ pub async fn create_user(
         created_date_time: chrono::offset::Utc::now().naive_local(),
     };
 
-    match service
-        .create_user(&db_pool.get_ref(), &new_person_record)
-        .await
-    {
-        Ok(_) => {}
-        Err(err) => {
-            error!("Error creating user: {:?}", err);
-            HttpResponse::InternalServerError().finish();
-        }
-    };
-
     match service
         .create_user(db_pool.get_ref(), &new_person_record)
         .await
@@ -144,7 +148,7 @@ pub async fn create_user(
         }),
         Err(e) => {
             error!("Error creating user: {:?}", e);
-            HttpResponse::InternalServerError().finish()
+            error_response(&e)
         }
     }
 }
@@ -177,7 +181,7 @@ pub async fn update_user(
         }),
         Err(e) => {
             error!("Error updating user: {:?}", e);
-            HttpResponse::InternalServerError().finish()
+            error_response(&e)
         }
     }
 }
@@ -192,7 +196,7 @@ pub async fn delete_user(
         Ok(false) => HttpResponse::NotFound().finish(),
         Err(e) => {
             error!("Error deleting user: {:?}", e);
-            HttpResponse::InternalServerError().finish()
+            error_response(&e)
         }
     }
 }
@@ -1,11 +1,15 @@
+mod auth;
 mod db;
 mod handlers;
 mod services;
 
 // Import necessary modules and crates
 use actix_web::{web, App, HttpServer};
+use auth::{Permission, RequirePermission};
+use db::Outbox;
 use deadpool_postgres::{Config, ConfigError, ManagerConfig, RecyclingMethod, Runtime};
-use services::PersonService;
+use services::{EventBroadcaster, MqttEventPublisher, OutboxRelay, PersonService};
+use std::time::Duration;
 use std::{ptr::null, sync::Arc};
 use tokio_postgres::{GenericClient, NoTls};
 use tracing::{error, info};
@@ -27,32 +31,68 @@ async fn main() -> std::io::Result<()> {
     });
     let pool = config.create_pool(Some(Runtime::Tokio1), NoTls).unwrap();
     let mut client = pool.get().await.unwrap();
-    let person_service: Arc<PersonService> = Arc::new(PersonService::new(&client).await.unwrap());
-    //let person_service: PersonService = PersonService::new(&client).await.unwrap();
+    let broadcaster = Arc::new(EventBroadcaster::new());
+    let person_service: Arc<PersonService> = Arc::new(
+        PersonService::new(&client, broadcaster.clone())
+            .await
+            .unwrap(),
+    );
+
+    let relay_outbox = Arc::new(Outbox::new(&client).await.unwrap());
+    let listen_dsn = std::env::var("PERSON_DB_DSN").unwrap_or_else(|_| {
+        "host=localhost port=5432 dbname=Person user=postgres password=5k4t3rd4t3r".to_string()
+    });
+    let mqtt_host = std::env::var("MQTT_HOST").unwrap_or_else(|_| "localhost".to_string());
+    let mqtt_port: u16 = std::env::var("MQTT_PORT")
+        .ok()
+        .and_then(|p| p.parse().ok())
+        .unwrap_or(1883);
+
+    let publisher = MqttEventPublisher::connect("personsvc-outbox-relay", &mqtt_host, mqtt_port);
+    let relay = OutboxRelay::new(
+        pool.clone(),
+        relay_outbox,
+        Arc::new(publisher),
+        Duration::from_secs(10),
+        listen_dsn,
+    );
+    tokio::spawn(relay.run());
+
     let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(person_service.clone()))
             //.app_data(person_service.clone())
             .app_data(web::Data::new(pool.clone()))
-            .route(
-                "/api/v1/person",
-                web::post().to(handlers::person_handler::create_user),
+            .app_data(web::Data::new(broadcaster.clone()))
+            .service(
+                web::resource("/api/v1/person")
+                    .route(web::post().to(handlers::person_handler::create_user))
+                    .wrap(RequirePermission::new(Permission::WritePerson)),
+            )
+            .service(
+                web::resource("/api/v1/person")
+                    .route(web::get().to(handlers::person_handler::list_users))
+                    .wrap(RequirePermission::new(Permission::ReadPerson)),
             )
-            .route(
-                "/api/v1/person",
-                web::get().to(handlers::person_handler::list_users),
+            .service(
+                web::resource("/api/v1/person/events")
+                    .route(web::get().to(handlers::events_handler::person_events))
+                    .wrap(RequirePermission::new(Permission::ReadPerson)),
             )
-            .route(
-                "/api/v1/person/{id}",
-                web::get().to(handlers::person_handler::get_user_by_id),
+            .service(
+                web::resource("/api/v1/person/{id}")
+                    .route(web::get().to(handlers::person_handler::get_user_by_id))
+                    .wrap(RequirePermission::new(Permission::ReadPerson)),
             )
-            .route(
-                "/api/v1/person/{id}",
-                web::put().to(handlers::person_handler::update_user),
+            .service(
+                web::resource("/api/v1/person/{id}")
+                    .route(web::put().to(handlers::person_handler::update_user))
+                    .wrap(RequirePermission::new(Permission::WritePerson)),
             )
-            .route(
-                "/api/v1/person/{id}",
-                web::delete().to(handlers::person_handler::delete_user),
+            .service(
+                web::resource("/api/v1/person/{id}")
+                    .route(web::delete().to(handlers::person_handler::delete_user))
+                    .wrap(RequirePermission::new(Permission::DeletePerson)),
             )
     })
     .bind("127.0.0.1:8080")?
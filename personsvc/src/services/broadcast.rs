@@ -0,0 +1,41 @@
+// services/broadcast.rs
+
+use tokio::sync::broadcast;
+
+/// Default number of buffered frames per subscriber before a slow client
+/// starts missing messages (`broadcast::channel` drops the oldest once
+/// full rather than blocking publishers).
+const CHANNEL_CAPACITY: usize = 256;
+
+/// Fans out JSON event frames to every connected WebSocket client.
+///
+/// Holds plain `String` frames rather than typed events —
+/// `PersonService::broadcast_on_commit` serializes each `PersonChanged` to
+/// JSON once before sending, so every subscriber gets the same text frame.
+#[derive(Clone)]
+pub struct EventBroadcaster {
+    sender: broadcast::Sender<String>,
+}
+
+impl EventBroadcaster {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<String> {
+        self.sender.subscribe()
+    }
+
+    /// Sends a frame to all current subscribers. Having zero subscribers is
+    /// the common case (no clients connected) and not an error.
+    pub fn send(&self, frame: String) {
+        let _ = self.sender.send(frame);
+    }
+}
+
+impl Default for EventBroadcaster {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,72 @@
+// services/error.rs
+
+use thiserror::Error;
+use tokio_postgres::error::SqlState;
+
+/// Typed failure surface for `PersonService`, so callers can branch on
+/// *why* a call failed instead of pattern-matching on `Box<dyn Error>`'s
+/// `to_string()`. Postgres errors are classified by SQLSTATE (see
+/// `From<tokio_postgres::Error>` below) rather than threaded through as
+/// opaque driver errors.
+#[derive(Debug, Error)]
+pub enum PersonServiceError {
+    #[error("person not found")]
+    NotFound,
+
+    #[error("timed out waiting for a database connection")]
+    PoolTimeout,
+
+    #[error("conflicts with existing data via constraint {constraint}")]
+    Conflict { constraint: String },
+
+    #[error("transaction could not be serialized; safe to retry")]
+    Serialization,
+
+    #[error("database error ({code} {severity}): {message}")]
+    Db {
+        code: String,
+        severity: String,
+        message: String,
+    },
+}
+
+impl From<tokio_postgres::Error> for PersonServiceError {
+    fn from(err: tokio_postgres::Error) -> Self {
+        let Some(db_err) = err.as_db_error() else {
+            return PersonServiceError::Db {
+                code: "08000".to_string(),
+                severity: "ERROR".to_string(),
+                message: err.to_string(),
+            };
+        };
+
+        match db_err.code() {
+            code if *code == SqlState::UNIQUE_VIOLATION => PersonServiceError::Conflict {
+                constraint: db_err.constraint().unwrap_or("unknown").to_string(),
+            },
+            code if *code == SqlState::T_R_SERIALIZATION_FAILURE
+                || *code == SqlState::T_R_DEADLOCK_DETECTED =>
+            {
+                PersonServiceError::Serialization
+            }
+            code => PersonServiceError::Db {
+                code: code.code().to_string(),
+                severity: db_err.severity().to_string(),
+                message: db_err.message().to_string(),
+            },
+        }
+    }
+}
+
+impl From<deadpool_postgres::PoolError> for PersonServiceError {
+    fn from(err: deadpool_postgres::PoolError) -> Self {
+        match err {
+            deadpool_postgres::PoolError::Timeout(_) => PersonServiceError::PoolTimeout,
+            other => PersonServiceError::Db {
+                code: "08000".to_string(),
+                severity: "ERROR".to_string(),
+                message: other.to_string(),
+            },
+        }
+    }
+}
@@ -0,0 +1,9 @@
+pub mod broadcast;
+pub mod error;
+pub mod outbox_relay;
+pub mod person_service;
+
+pub use broadcast::EventBroadcaster;
+pub use error::PersonServiceError;
+pub use outbox_relay::{EventPublisher, MqttEventPublisher, OutboxRelay};
+pub use person_service::PersonService;
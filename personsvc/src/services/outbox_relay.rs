@@ -0,0 +1,370 @@
+// services/outbox_relay.rs
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use tokio::sync::{oneshot, Mutex};
+use tracing::{error, info};
+
+use crate::db::conn::DbConn;
+use crate::db::outbox::{Outbox, OutboxMessage};
+
+/// `EventPublisher` backed by an MQTT broker via `rumqttc`. The MQTT topic
+/// is `{outbox topic}/{event name}` (e.g. `user_events/user_added`) so
+/// subscribers can filter with MQTT wildcards instead of parsing headers.
+pub struct MqttEventPublisher {
+    client: rumqttc::AsyncClient,
+    /// Completed in FIFO order as the event loop observes `PubAck`s.
+    /// `rumqttc` acks QoS 1 publishes in the order they were sent for a
+    /// given client session, so a plain queue is enough to pair each ack
+    /// with the call that's waiting on it. Each entry carries the id it was
+    /// pushed under so a `publish` call that times out (see `ACK_TIMEOUT`)
+    /// can remove exactly its own waiter instead of leaving it in the queue
+    /// to steal a later, unrelated ack. Only pushed to once
+    /// `AsyncClient::publish` itself has succeeded, so a failed send never
+    /// leaves an orphaned waiter behind either.
+    pending_acks: Arc<Mutex<VecDeque<(u64, oneshot::Sender<()>)>>>,
+    next_ack_id: AtomicU64,
+}
+
+/// How long `publish` waits for a broker PUBACK before giving up. Without a
+/// bound, a broker outage would hang the ack wait forever, which in turn
+/// pins the relay's open transaction (and its row locks) on the first row
+/// of the batch indefinitely instead of letting the row flow into
+/// `errored_event` so backoff can engage.
+const ACK_TIMEOUT: Duration = Duration::from_secs(10);
+
+impl MqttEventPublisher {
+    /// Connects to the broker at `host:port` and spawns the background
+    /// task that drives the `rumqttc` event loop — `rumqttc` only actually
+    /// sends packets while something is polling `EventLoop::poll()`.
+    pub fn connect(client_id: &str, host: &str, port: u16) -> Self {
+        let mut options = rumqttc::MqttOptions::new(client_id, host, port);
+        options.set_keep_alive(Duration::from_secs(30));
+
+        let (client, mut event_loop) = rumqttc::AsyncClient::new(options, 64);
+        let pending_acks = Arc::new(Mutex::new(VecDeque::new()));
+        let acks = pending_acks.clone();
+        tokio::spawn(async move {
+            loop {
+                match event_loop.poll().await {
+                    Ok(rumqttc::Event::Incoming(rumqttc::Packet::PubAck(_))) => {
+                        if let Some((_, waiter)) = acks.lock().await.pop_front() {
+                            let _ = waiter.send(());
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        error!("MQTT event loop error: {:?}", err);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+            }
+        });
+
+        Self {
+            client,
+            pending_acks,
+            next_ack_id: AtomicU64::new(0),
+        }
+    }
+}
+
+#[async_trait]
+impl EventPublisher for MqttEventPublisher {
+    async fn publish(
+        &self,
+        topic: &str,
+        event: &str,
+        payload: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mqtt_topic = format!("{}/{}", topic, event);
+
+        self.client
+            .publish(mqtt_topic, rumqttc::QoS::AtLeastOnce, false, payload.to_string())
+            .await?;
+
+        // `AsyncClient::publish` only enqueues the packet onto the event
+        // loop's channel and returns once that's done, well before the
+        // broker PUBACKs it. Registering a waiter only now (after the send
+        // itself succeeded) and blocking on it is what actually gives
+        // callers "cleared only once the broker acked" semantics, so a
+        // crash between clearing the outbox row and the packet reaching the
+        // broker can't drop a message — and bounding the wait means a
+        // broker that never acks surfaces as an `Err` instead of hanging
+        // the caller (and the transaction it's running inside) forever.
+        let ack_id = self.next_ack_id.fetch_add(1, Ordering::Relaxed);
+        let (ack_tx, ack_rx) = oneshot::channel();
+        self.pending_acks.lock().await.push_back((ack_id, ack_tx));
+
+        match tokio::time::timeout(ACK_TIMEOUT, ack_rx).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(_)) => Err("MQTT event loop dropped before acking publish".into()),
+            Err(_) => {
+                // Drop our own waiter out of the queue rather than leaving
+                // it for a future ack to resolve — otherwise the next
+                // unrelated PubAck would complete this stale wait and every
+                // pairing after it would be off by one.
+                self.pending_acks
+                    .lock()
+                    .await
+                    .retain(|(id, _)| *id != ack_id);
+                Err(format!(
+                    "timed out after {:?} waiting for broker PUBACK",
+                    ACK_TIMEOUT
+                )
+                .into())
+            }
+        }
+    }
+}
+
+/// Abstraction over the message broker the outbox relay publishes to.
+///
+/// Kept separate from `Outbox` so the relay isn't hard-wired to a specific
+/// broker client. `MqttEventPublisher` publishes over MQTT via `rumqttc`;
+/// the test-only `InMemoryEventPublisher` below stands in for a broker in
+/// unit tests.
+#[async_trait]
+pub trait EventPublisher: Send + Sync {
+    async fn publish(
+        &self,
+        topic: &str,
+        event: &str,
+        payload: &str,
+    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>>;
+}
+
+/// The number of rows pulled off the Outbox table per drain iteration.
+const BATCH_SIZE: i64 = 50;
+
+/// Polls the Outbox table and publishes pending messages to the configured
+/// broker, clearing or erroring each row based on the publish result.
+///
+/// This is the drain side of the transactional-outbox pattern: `Outbox::insert`
+/// already writes rows transactionally alongside the person mutation, but
+/// nothing read them back out until this worker existed.
+pub struct OutboxRelay {
+    db_pool: Pool,
+    outbox: Arc<Outbox>,
+    publisher: Arc<dyn EventPublisher>,
+    /// How often to drain even if no `outbox_new` notification arrives —
+    /// covers rows inserted before the listener connected and notifications
+    /// lost to a dropped connection.
+    fallback_interval: Duration,
+    /// Connection string for the dedicated `LISTEN` connection. Needs its
+    /// own connection (not one borrowed from `db_pool`) because it blocks
+    /// on notifications for the lifetime of the relay.
+    listen_dsn: String,
+}
+
+impl OutboxRelay {
+    pub fn new(
+        db_pool: Pool,
+        outbox: Arc<Outbox>,
+        publisher: Arc<dyn EventPublisher>,
+        fallback_interval: Duration,
+        listen_dsn: String,
+    ) -> Self {
+        Self {
+            db_pool,
+            outbox,
+            publisher,
+            fallback_interval,
+            listen_dsn,
+        }
+    }
+
+    /// Runs the drain loop forever. Intended to be spawned as a background
+    /// task alongside the `HttpServer` in `main.rs`. Drains immediately on
+    /// an `outbox_new` notification, and otherwise on the fallback timer.
+    pub async fn run(self) {
+        let (notify_tx, mut notify_rx) = tokio::sync::mpsc::channel::<()>(1);
+        tokio::spawn(listen_for_notifications(self.listen_dsn.clone(), notify_tx));
+
+        let mut ticker = tokio::time::interval(self.fallback_interval);
+        let mut listening = true;
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                received = notify_rx.recv(), if listening => {
+                    if received.is_none() {
+                        listening = false;
+                        error!("Outbox notification listener exited; relying on fallback polling only");
+                    }
+                }
+            }
+
+            if let Err(err) = self.drain_once().await {
+                error!("Outbox relay iteration failed: {:?}", err);
+            }
+        }
+    }
+
+    async fn drain_once(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let mut client = self.db_pool.get().await?;
+        let txn = client.build_transaction().start().await?;
+
+        let conn = DbConn::Tx(&txn);
+        let messages = self.outbox.get_pending_messages(&conn).await?;
+        if messages.is_empty() {
+            txn.rollback().await?;
+            return Ok(());
+        }
+
+        info!("Outbox relay draining {} pending message(s)", messages.len());
+        for message in messages.into_iter().take(BATCH_SIZE as usize) {
+            self.publish_one(&conn, message).await;
+        }
+
+        txn.commit().await?;
+        Ok(())
+    }
+
+    /// Publishes one claimed row to the broker and marks it accordingly.
+    ///
+    /// chunk0-5 originally asked for this worker to also fan the message
+    /// out to WebSocket subscribers on a successful publish. That was
+    /// superseded by chunk1-5's `PersonService::broadcast_on_commit`, which
+    /// emits the same event from the mutation's post-commit hook instead —
+    /// deliberately, since that fires the instant the write commits rather
+    /// than waiting on however long this relay takes to drain the row, and
+    /// doesn't depend on the broker being reachable. This method stays
+    /// broker-only on purpose; it does not also call into
+    /// `EventBroadcaster`.
+    async fn publish_one(&self, conn: &DbConn<'_>, message: OutboxMessage) {
+        let result = self
+            .publisher
+            .publish(&message.topic, &message.event_name, &message.payload)
+            .await;
+
+        match result {
+            Ok(()) => {
+                if let Err(err) = self.outbox.clear_event(conn, message.id).await {
+                    error!("Failed to clear published outbox row {}: {:?}", message.id, err);
+                }
+            }
+            Err(err) => {
+                error!("Failed to publish outbox row {}: {:?}", message.id, err);
+                let reason = err.to_string();
+                if let Err(err) = self.outbox.errored_event(conn, message.id, &reason).await {
+                    error!("Failed to mark outbox row {} as errored: {:?}", message.id, err);
+                }
+            }
+        }
+    }
+}
+
+/// Opens a dedicated connection, issues `LISTEN outbox_new`, and forwards a
+/// wakeup on `notify_tx` for every notification received. Reconnects with a
+/// short backoff if the connection drops; the relay's fallback timer covers
+/// the gap while it does.
+async fn listen_for_notifications(dsn: String, notify_tx: tokio::sync::mpsc::Sender<()>) {
+    loop {
+        match tokio_postgres::connect(&dsn, tokio_postgres::NoTls).await {
+            Ok((client, mut connection)) => {
+                let (notif_tx, mut notif_rx) = tokio::sync::mpsc::unbounded_channel();
+                let driver = futures_util::future::poll_fn(move |cx| {
+                    use std::task::Poll;
+                    loop {
+                        match connection.poll_message(cx) {
+                            Poll::Ready(Some(Ok(msg))) => {
+                                if let tokio_postgres::AsyncMessage::Notification(_) = msg {
+                                    let _ = notif_tx.send(());
+                                }
+                            }
+                            Poll::Ready(Some(Err(err))) => {
+                                error!("Outbox LISTEN connection error: {:?}", err);
+                                return Poll::Ready(());
+                            }
+                            Poll::Ready(None) => return Poll::Ready(()),
+                            Poll::Pending => return Poll::Pending,
+                        }
+                    }
+                });
+                tokio::spawn(driver);
+
+                if let Err(err) = client.batch_execute("LISTEN outbox_new").await {
+                    error!("Failed to LISTEN on outbox_new: {:?}", err);
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    continue;
+                }
+
+                while notif_rx.recv().await.is_some() {
+                    let _ = notify_tx.try_send(());
+                }
+            }
+            Err(err) => {
+                error!("Outbox LISTEN connection failed: {:?}", err);
+            }
+        }
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// In-memory `EventPublisher` that just records calls instead of
+    /// talking to a broker — the "in-memory collector" chunk0-1 introduced
+    /// `EventPublisher` to enable, which nothing had actually exercised
+    /// until this test.
+    #[derive(Default)]
+    struct InMemoryEventPublisher {
+        published: Mutex<Vec<(String, String, String)>>,
+    }
+
+    #[async_trait]
+    impl EventPublisher for InMemoryEventPublisher {
+        async fn publish(
+            &self,
+            topic: &str,
+            event: &str,
+            payload: &str,
+        ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+            self.published.lock().await.push((
+                topic.to_string(),
+                event.to_string(),
+                payload.to_string(),
+            ));
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn in_memory_publisher_collects_calls_in_order() {
+        let publisher = InMemoryEventPublisher::default();
+
+        publisher
+            .publish("user_events", "user_added", "{\"id\":1}")
+            .await
+            .unwrap();
+        publisher
+            .publish("user_events", "user_updated", "{\"id\":1}")
+            .await
+            .unwrap();
+
+        let published = publisher.published.lock().await;
+        assert_eq!(
+            *published,
+            vec![
+                (
+                    "user_events".to_string(),
+                    "user_added".to_string(),
+                    "{\"id\":1}".to_string()
+                ),
+                (
+                    "user_events".to_string(),
+                    "user_updated".to_string(),
+                    "{\"id\":1}".to_string()
+                ),
+            ]
+        );
+    }
+}
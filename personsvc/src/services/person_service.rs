@@ -1,68 +1,234 @@
 // services/person_service.rs
 
-use std::error::Error;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
 
+use crate::db::conn::DbConn;
 use crate::db::outbox::Outbox;
-use crate::db::person::{NewPersonRecord, PersonDb, PersonRecord};
+use crate::db::person::{NewPersonRecord, PersonDb, PersonRecord, PersonRepository};
+use crate::services::broadcast::EventBroadcaster;
+use crate::services::error::PersonServiceError;
 use deadpool_postgres::{Pool, Transaction};
 use opentelemetry::global::{self, ObjectSafeSpan};
 use opentelemetry::trace::{Span, Tracer};
-use serde_json::json;
+use rand::Rng;
+use serde::Serialize;
 use tokio_postgres::Client;
-use tracing::{error, info};
+use tracing::error;
 use uuid::Uuid;
 
+/// Side-effect callbacks queued up by a `with_transaction` closure and run
+/// only after the transaction actually commits, so a rolled-back write
+/// never triggers things like outbound notifications.
+pub type OnCommit = Vec<Box<dyn FnOnce() + Send>>;
+
+type TxFuture<'a, T> =
+    Pin<Box<dyn Future<Output = Result<(T, OnCommit), PersonServiceError>> + Send + 'a>>;
+
+/// How many times `with_transaction` retries a transaction that failed with
+/// a serialization conflict before giving up and surfacing the error.
+const MAX_SERIALIZATION_RETRIES: u32 = 5;
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(20);
+const RETRY_MAX_DELAY: Duration = Duration::from_millis(500);
+
+/// Exponential backoff with full jitter for retrying a transaction after
+/// `attempt` prior failures (0-indexed): doubles the base delay per attempt,
+/// caps it, then picks uniformly in `[0, cap]` so concurrent retriers spread
+/// out instead of converging on the same next attempt.
+fn retry_delay(attempt: u32) -> Duration {
+    let cap_ms = RETRY_BASE_DELAY
+        .saturating_mul(1 << attempt.min(16))
+        .min(RETRY_MAX_DELAY)
+        .as_millis()
+        .max(1) as u64;
+    Duration::from_millis(rand::thread_rng().gen_range(0..=cap_ms))
+}
+
+/// A person mutation that has actually committed, fanned out to WebSocket
+/// subscribers via `EventBroadcaster`. Always carries the aggregate id so a
+/// client can reconcile its local cache even for a `Deleted` frame, which
+/// has no record left to send.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum PersonChanged {
+    #[serde(rename = "PersonCreated")]
+    Added { record: PersonRecord },
+    #[serde(rename = "PersonUpdated")]
+    Updated { record: PersonRecord },
+    #[serde(rename = "PersonDeleted")]
+    Deleted { id: Uuid },
+}
+
 pub struct PersonService {
-    person_db: PersonDb,
+    person_db: Arc<dyn PersonRepository>,
     outbox: Outbox,
+    broadcaster: Arc<EventBroadcaster>,
 }
 
 impl PersonService {
-    pub async fn new(client: &Client) -> Result<Self, Box<dyn Error>> {
+    pub async fn new(
+        client: &Client,
+        broadcaster: Arc<EventBroadcaster>,
+    ) -> Result<Self, PersonServiceError> {
         match PersonDb::new(&client).await {
             Ok(user_name_db) => match Outbox::new(&client).await {
                 Ok(outbox) => Ok(PersonService {
-                    person_db: user_name_db,
+                    person_db: Arc::new(user_name_db),
                     outbox,
+                    broadcaster,
                 }),
                 Err(err) => {
                     error!("Failed to initialize Outbox: {:?}", err);
-                    return Err(Box::new(err));
+                    Err(err.into())
                 }
             },
             Err(err) => {
                 error!("Failed to initialize UserNameDb: {:?}", err);
-                return Err(Box::new(err));
+                Err(err.into())
             }
         }
     }
 
+    /// Builds a service around an arbitrary `PersonRepository` while still
+    /// going through the normal `Outbox::new` setup. Lets a caller swap the
+    /// person storage backend (e.g. a different schema or a read replica)
+    /// without touching outbox/broadcast wiring — the `Outbox` itself still
+    /// prepares statements against `client`, so this does not avoid needing
+    /// a live Postgres connection; making that pluggable too is future work.
+    pub async fn with_repository(
+        repository: Arc<dyn PersonRepository>,
+        client: &Client,
+        broadcaster: Arc<EventBroadcaster>,
+    ) -> Result<Self, PersonServiceError> {
+        match Outbox::new(&client).await {
+            Ok(outbox) => Ok(PersonService {
+                person_db: repository,
+                outbox,
+                broadcaster,
+            }),
+            Err(err) => {
+                error!("Failed to initialize Outbox: {:?}", err);
+                Err(err.into())
+            }
+        }
+    }
+
+    /// Queues a `PersonChanged` frame as an `OnCommit` hook rather than
+    /// sending it immediately, so a later rollback of the same transaction
+    /// can never result in a client seeing an event for a write that didn't
+    /// happen.
+    fn broadcast_on_commit(&self, change: PersonChanged) -> OnCommit {
+        let broadcaster = self.broadcaster.clone();
+        vec![Box::new(move || match serde_json::to_string(&change) {
+            Ok(frame) => broadcaster.send(frame),
+            Err(err) => error!("Failed to serialize PersonChanged frame: {:?}", err),
+        })]
+    }
+
+    /// Runs `f` inside a freshly opened transaction, retrying the whole
+    /// attempt up to `MAX_SERIALIZATION_RETRIES` times if it fails with a
+    /// retryable serialization error (SQLSTATE `40001`/`40P01`). `f` must be
+    /// re-runnable (`Fn`, not `FnOnce`) and side-effect-free until commit,
+    /// since a poisoned transaction can't be reused — each attempt opens a
+    /// fresh one. Rolls back automatically on a non-retryable `Err`, commits
+    /// on `Ok`, and only then runs the `OnCommit` callbacks `f` returned
+    /// alongside its value.
+    async fn with_transaction<T, F>(&self, db_pool: &Pool, f: F) -> Result<T, PersonServiceError>
+    where
+        T: Send,
+        F: for<'a> Fn(&'a Transaction<'a>) -> TxFuture<'a, T>,
+    {
+        let mut attempt: u32 = 0;
+        loop {
+            match self.try_transaction_once(db_pool, &f).await {
+                Err(PersonServiceError::Serialization) if attempt < MAX_SERIALIZATION_RETRIES => {
+                    let delay = retry_delay(attempt);
+                    error!(
+                        "Transaction hit a serialization conflict, retrying in {:?} (attempt {}/{})",
+                        delay, attempt + 1, MAX_SERIALIZATION_RETRIES
+                    );
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                other => return other,
+            }
+        }
+    }
+
+    async fn try_transaction_once<T, F>(&self, db_pool: &Pool, f: &F) -> Result<T, PersonServiceError>
+    where
+        T: Send,
+        F: for<'a> Fn(&'a Transaction<'a>) -> TxFuture<'a, T>,
+    {
+        let mut client = match db_pool.get().await {
+            Ok(c) => c,
+            Err(err) => {
+                error!("Error getting db client from pool: {:?}", err);
+                return Err(err.into());
+            }
+        };
+
+        let tx = match client.build_transaction().start().await {
+            Ok(t) => t,
+            Err(err) => {
+                error!("Error starting transaction: {:?}", err);
+                return Err(err.into());
+            }
+        };
+
+        let (value, on_commit) = match f(&tx).await {
+            Ok(result) => result,
+            Err(err) => {
+                if let Err(e) = tx.rollback().await {
+                    error!("Error rolling back transaction: {:?}", e);
+                    return Err(e.into());
+                }
+                return Err(err);
+            }
+        };
+
+        if let Err(err) = tx.commit().await {
+            error!("Error committing transaction: {:?}", err);
+            return Err(err.into());
+        }
+
+        for hook in on_commit {
+            hook();
+        }
+
+        Ok(value)
+    }
+
     pub async fn get_user_by_id(
         &self,
         db_pool: &Pool,
         id: Uuid,
-    ) -> Result<Option<PersonRecord>, Box<dyn Error>> {
+    ) -> Result<Option<PersonRecord>, PersonServiceError> {
         let mut span = global::tracer("person_service").start("get_user_by_id");
 
-        let mut client = match db_pool.get().await {
+        let client = match db_pool.get().await {
             Ok(c) => c,
             Err(err) => {
                 error!("Error getting db client from pool: {:?}", err);
-                return Err(Box::new(err));
+                ObjectSafeSpan::end(&mut span);
+                return Err(err.into());
             }
         };
 
-        let user = match self.person_db.get_by_id(&client, id).await {
+        let conn = DbConn::Pooled(&client);
+        let user = match self.person_db.get_by_id(&conn, id).await {
             Ok(u) => u,
             Err(err) => {
                 error!("Failed to get user by ID: {:?}", err);
                 ObjectSafeSpan::end(&mut span);
-                return Err(Box::new(err));
+                return Err(err.into());
             }
         };
 
         ObjectSafeSpan::end(&mut span);
-        return Ok(user);
+        Ok(user)
     }
 
     pub async fn list_users(
@@ -70,251 +236,147 @@ impl PersonService {
         db_pool: &Pool,
         offset: i64,
         limit: i64,
-    ) -> Result<Vec<PersonRecord>, Box<dyn Error>> {
+    ) -> Result<Vec<PersonRecord>, PersonServiceError> {
         let mut span = global::tracer("person_service").start("list_users");
         let client = match db_pool.get().await {
             Ok(c) => c,
             Err(err) => {
                 error!("Error getting db client from pool: {:?}", err);
-                return Err(Box::new(err));
+                ObjectSafeSpan::end(&mut span);
+                return Err(err.into());
             }
         };
 
-        let users = match self.person_db.list(&client, offset, limit).await {
+        let conn = DbConn::Pooled(&client);
+        let users = match self.person_db.list(&conn, offset, limit).await {
             Ok(u) => u,
             Err(err) => {
                 ObjectSafeSpan::end(&mut span);
                 error!("Failed to list users: {:?}", err);
-                return Err(Box::new(err));
+                return Err(err.into());
             }
         };
 
         ObjectSafeSpan::end(&mut span);
 
-        return Ok(users);
+        Ok(users)
     }
 
     pub async fn create_user(
         &self,
         db_pool: &Pool,
         user: &NewPersonRecord,
-    ) -> Result<PersonRecord, Box<dyn Error>> {
+    ) -> Result<PersonRecord, PersonServiceError> {
         let mut span = global::tracer("person_service").start("create_user");
+        let user = user.clone();
 
-        let mut client = match db_pool.get().await {
-            Ok(c) => c,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error getting db client from pool: {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
-
-        let tx = match client.build_transaction().start().await {
-            Ok(t) => t,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error starting transaction for Create Person {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
+        let result = self
+            .with_transaction(db_pool, move |tx| {
+                let user = user.clone();
+                Box::pin(async move {
+                    let conn = DbConn::Tx(tx);
+                    let created_user = self.person_db.create(&conn, &user).await.map_err(|err| {
+                        error!("Failed to create user: {:?}", err);
+                        PersonServiceError::from(err)
+                    })?;
 
-        let created_user = match self.person_db.create(&tx, user).await {
-            Ok(user) => user,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Failed to create user: {:?}", err);
-                let _ = match tx.rollback().await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("Failed to rollback transaction for Create User: {:?}", e);
-                        return Err(Box::new(e));
-                    }
-                };
-                return Err(Box::new(err));
-            }
-        };
+                    self.outbox
+                        .insert(&conn, "user_events", "user_added", &created_user)
+                        .await
+                        .map_err(|err| {
+                            error!("Failed to create outbox record: {:?}", err);
+                            PersonServiceError::from(err)
+                        })?;
 
-        let _ = match self
-            .outbox
-            .insert(&tx, "user_events", "user_added", &created_user)
-            .await
-        {
-            Ok(o) => o,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Failed to create outbox record: {:?}", err);
-                let _ = match tx.rollback().await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!(
-                            "Failed to rollback transaction for Create Person Outbox record: {:?}",
-                            e
-                        );
-                        return Err(Box::new(e));
-                    }
-                };
-                return Err(Box::new(err));
-            }
-        };
+                    let on_commit = self.broadcast_on_commit(PersonChanged::Added {
+                        record: created_user.clone(),
+                    });
+                    Ok((created_user, on_commit))
+                })
+            })
+            .await;
 
-        let _ = match tx.commit().await {
-            Ok(_) => {}
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error committing transaction for Create Person: {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
         ObjectSafeSpan::end(&mut span);
-        return Ok(created_user);
+        result
     }
 
     pub async fn update_user(
         &self,
         db_pool: &Pool,
         user: &PersonRecord,
-    ) -> Result<PersonRecord, Box<dyn Error>> {
+    ) -> Result<PersonRecord, PersonServiceError> {
         let mut span = global::tracer("person_service").start("update_user");
+        let user = user.clone();
 
-        let mut client = match db_pool.get().await {
-            Ok(c) => c,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error getting db client from pool: {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
+        let result = self
+            .with_transaction(db_pool, move |tx| {
+                let user = user.clone();
+                Box::pin(async move {
+                    let conn = DbConn::Tx(tx);
+                    let updated_user = self.person_db.update(&conn, &user).await.map_err(|err| {
+                        error!("Failed to update user: {:?}", err);
+                        PersonServiceError::from(err)
+                    })?;
 
-        let tx = match client.build_transaction().start().await {
-            Ok(t) => t,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error starting transaction for Create Person {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
+                    self.outbox
+                        .insert(&conn, "user_events", "user_updated", &updated_user)
+                        .await
+                        .map_err(|err| {
+                            error!(
+                                "Failed to create outbox record for Update Person: {:?}",
+                                err
+                            );
+                            PersonServiceError::from(err)
+                        })?;
 
-        let updated_user = match self.person_db.update(&tx, user).await {
-            Ok(u) => u,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Failed to update user: {:?}", err);
-                let _ = match tx.rollback().await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("Error rolling transaction for Update Person {:?}", e);
-                        return Err(Box::new(e));
-                    }
-                };
-                return Err(Box::new(err));
-            }
-        };
+                    let on_commit = self.broadcast_on_commit(PersonChanged::Updated {
+                        record: updated_user.clone(),
+                    });
+                    Ok((updated_user, on_commit))
+                })
+            })
+            .await;
 
-        let _ = match self
-            .outbox
-            .insert(&tx, "user_events", "user_updated", &updated_user)
-            .await
-        {
-            Ok(a) => a,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                let _ = match tx.rollback().await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!(
-                            "Failed to rollback transaction for Create Person Outbox record: {:?}",
-                            e
-                        );
-                        return Err(Box::new(e));
-                    }
-                };
-                error!(
-                    "Failed to create outbox record For Update Person: {:?}",
-                    err
-                );
-                return Err(Box::new(err));
-            }
-        };
-        let _ = match tx.commit().await {
-            Ok(_) => {}
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error committing transaction for Update Person: {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
         ObjectSafeSpan::end(&mut span);
-        return Ok(updated_user);
+        result
     }
 
-    pub async fn delete_user(&self, db_pool: &Pool, id: &Uuid) -> Result<bool, Box<dyn Error>> {
+    pub async fn delete_user(
+        &self,
+        db_pool: &Pool,
+        id: &Uuid,
+    ) -> Result<bool, PersonServiceError> {
         let mut span = global::tracer("person_service").start("delete_user");
+        let id = *id;
 
-        let mut client = match db_pool.get().await {
-            Ok(c) => c,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error getting db client from pool: {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
+        let result = self
+            .with_transaction(db_pool, move |tx| {
+                Box::pin(async move {
+                    let conn = DbConn::Tx(tx);
+                    let deleted = self.person_db.delete(&conn, &id).await.map_err(|err| {
+                        error!("Error deleting Person {:?}", err);
+                        PersonServiceError::from(err)
+                    })?;
 
-        let tx = match client.build_transaction().start().await {
-            Ok(t) => t,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error starting transaction for Create Person {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
+                    let mut on_commit = Vec::new();
+                    if deleted {
+                        self.outbox
+                            .insert(&conn, "user_events", "user_deleted", &id)
+                            .await
+                            .map_err(|err| {
+                                error!("Error recording outbox event for Delete Person {:?}", err);
+                                PersonServiceError::from(err)
+                            })?;
 
-        let deleted = match self.person_db.delete(&tx, &id).await {
-            Ok(d) => d,
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error deleting Person {:?}", err);
-                let _ = match tx.rollback().await {
-                    Ok(_) => {}
-                    Err(e) => {
-                        error!("Error rolling back transaction for Delete Person {:?}", err);
-                        return Err(Box::new(e));
+                        on_commit = self.broadcast_on_commit(PersonChanged::Deleted { id });
                     }
-                };
-                return Err(Box::new(err));
-            }
-        };
-        if (deleted) {
-            let _ = match self
-                .outbox
-                .insert(&tx, "user_events", "user_deleted", &id)
-                .await
-            {
-                Ok(_) => {}
-                Err(err) => {
-                    ObjectSafeSpan::end(&mut span);
-                    error!("Error deleting Person {:?}", err);
-                    let _ = match tx.rollback().await {
-                        Ok(_) => {}
-                        Err(e) => {
-                            error!("Error committing transaction for Delete Person {:?}", e);
-                            return Err(Box::new(e));
-                        }
-                    };
-                    return Err(Box::new(err));
-                }
-            };
-        }
-        let _ = match tx.commit().await {
-            Ok(_) => {}
-            Err(err) => {
-                ObjectSafeSpan::end(&mut span);
-                error!("Error committing transaction for Delete Person: {:?}", err);
-                return Err(Box::new(err));
-            }
-        };
+
+                    Ok((deleted, on_commit))
+                })
+            })
+            .await;
 
         ObjectSafeSpan::end(&mut span);
-        return Ok(deleted);
+        result
     }
 }